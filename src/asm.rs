@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fmt;
+
+/// Errors that can occur while assembling SSBC mnemonics into machine code.
+#[derive(Debug)]
+pub enum AsmError {
+    /// An unrecognized mnemonic appeared on the given line.
+    UnknownMnemonic(String, usize),
+    /// A mnemonic that takes an operand didn't get one.
+    MissingOperand(String, usize),
+    /// An operand couldn't be parsed as a number.
+    InvalidOperand(String, usize),
+    /// An operand referenced a label that was never defined.
+    UndefinedLabel(String, usize),
+    /// An operand's value doesn't fit in the byte/word the mnemonic expects.
+    OperandOverflow(String, usize),
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsmError::UnknownMnemonic(mnemonic, line) => {
+                write!(f, "line {line}: unknown mnemonic {mnemonic:?}")
+            }
+            AsmError::MissingOperand(mnemonic, line) => {
+                write!(f, "line {line}: {mnemonic} requires an operand")
+            }
+            AsmError::InvalidOperand(operand, line) => {
+                write!(f, "line {line}: invalid operand {operand:?}")
+            }
+            AsmError::UndefinedLabel(label, line) => {
+                write!(f, "line {line}: undefined label {label:?}")
+            }
+            AsmError::OperandOverflow(operand, line) => {
+                write!(f, "line {line}: operand {operand:?} overflows")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// How an instruction's operand, if any, is encoded after its opcode byte.
+#[derive(Clone, Copy)]
+enum OperandKind {
+    /// No operand; the instruction is a single opcode byte.
+    None,
+    /// A single immediate byte, e.g. `pushimm 42`.
+    Imm,
+    /// A 16-bit address, emitted hi byte then lo byte to match `read_ext`.
+    /// May be given as a numeric literal or a label.
+    Ext,
+}
+
+/// Opcode and operand shape for each mnemonic `step` understands.
+fn mnemonic_info(mnemonic: &str) -> Option<(u8, OperandKind)> {
+    Some(match mnemonic {
+        "nop" => (0, OperandKind::None),
+        "halt" => (1, OperandKind::None),
+        "pushimm" => (2, OperandKind::Imm),
+        "pushext" => (3, OperandKind::Ext),
+        "popinh" => (4, OperandKind::None),
+        "popext" => (5, OperandKind::Ext),
+        "jnz" => (6, OperandKind::Ext),
+        "jnn" => (7, OperandKind::Ext),
+        "add" => (8, OperandKind::None),
+        "sub" => (9, OperandKind::None),
+        "nor" => (10, OperandKind::None),
+        _ => return None,
+    })
+}
+
+fn operand_size(kind: OperandKind) -> u32 {
+    match kind {
+        OperandKind::None => 1,
+        OperandKind::Imm => 2,
+        OperandKind::Ext => 3,
+    }
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal literal.
+fn parse_number(s: &str) -> Option<u32> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse::<u32>().ok(),
+    }
+}
+
+struct Instruction {
+    lineno: usize,
+    operand: Option<String>,
+    opcode: u8,
+    kind: OperandKind,
+}
+
+/// Assembles SSBC mnemonics into machine-code bytes, one byte per line of the `mac`
+/// format that `SsbcCli::reset` loads once written out as 8-digit binary.
+///
+/// This is a two-pass assembler: the first pass walks the source assigning each
+/// instruction an address and recording `label:` definitions in a symbol table, and the
+/// second emits opcode bytes, resolving any label operands against that table.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut symbols: HashMap<String, u16> = HashMap::new();
+    let mut instructions = Vec::new();
+    let mut addr: u32 = 0;
+
+    for (i, raw_line) in source.lines().enumerate() {
+        let lineno = i + 1;
+        let line = raw_line.split(';').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut tokens = line.split_whitespace();
+        let mut head = tokens.next().expect("non-empty line has a first token");
+
+        if let Some(label) = head.strip_suffix(':') {
+            let label_addr: u16 = addr
+                .try_into()
+                .map_err(|_| AsmError::OperandOverflow(label.to_string(), lineno))?;
+            symbols.insert(label.to_string(), label_addr);
+            head = match tokens.next() {
+                Some(next) => next,
+                None => continue,
+            };
+        }
+
+        let mnemonic = head.to_lowercase();
+        let (opcode, kind) = mnemonic_info(&mnemonic)
+            .ok_or_else(|| AsmError::UnknownMnemonic(mnemonic.clone(), lineno))?;
+        let operand = tokens.next().map(str::to_string);
+        if matches!(kind, OperandKind::Imm | OperandKind::Ext) && operand.is_none() {
+            return Err(AsmError::MissingOperand(mnemonic, lineno));
+        }
+
+        addr += operand_size(kind);
+        instructions.push(Instruction { lineno, operand, opcode, kind });
+    }
+
+    let mut out = Vec::new();
+    for instr in instructions {
+        out.push(instr.opcode);
+        match instr.kind {
+            OperandKind::None => {}
+            OperandKind::Imm => {
+                let operand = instr.operand.expect("Imm operand checked during pass one");
+                let value = parse_number(&operand)
+                    .ok_or_else(|| AsmError::InvalidOperand(operand.clone(), instr.lineno))?;
+                let byte: u8 = value
+                    .try_into()
+                    .map_err(|_| AsmError::OperandOverflow(operand.clone(), instr.lineno))?;
+                out.push(byte);
+            }
+            OperandKind::Ext => {
+                let operand = instr.operand.expect("Ext operand checked during pass one");
+                let value: u32 = match parse_number(&operand) {
+                    Some(n) => n,
+                    None => *symbols
+                        .get(&operand)
+                        .ok_or_else(|| AsmError::UndefinedLabel(operand.clone(), instr.lineno))?
+                        as u32,
+                };
+                let value: u16 = value
+                    .try_into()
+                    .map_err(|_| AsmError::OperandOverflow(operand.clone(), instr.lineno))?;
+                out.push((value / 0x100) as u8);
+                out.push((value % 0x100) as u8);
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::disassemble_range;
+    use crate::Memory;
+
+    #[test]
+    fn assembles_forward_label_reference() {
+        let source = "
+            jnz end
+            nop
+            end: halt
+        ";
+        let bytes = assemble(source).unwrap();
+        assert_eq!(bytes, vec![6, 0x00, 0x04, 0, 1]);
+    }
+
+    #[test]
+    fn assembles_backward_label_reference() {
+        let source = "
+            loop: nop
+            jnz loop
+        ";
+        let bytes = assemble(source).unwrap();
+        assert_eq!(bytes, vec![0, 6, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn pushimm_operand_overflow_is_rejected() {
+        let err = assemble("pushimm 0x100").unwrap_err();
+        assert!(matches!(err, AsmError::OperandOverflow(operand, 1) if operand == "0x100"));
+    }
+
+    #[test]
+    fn undefined_label_is_rejected() {
+        let err = assemble("jnz nowhere").unwrap_err();
+        assert!(matches!(err, AsmError::UndefinedLabel(label, 1) if label == "nowhere"));
+    }
+
+    #[test]
+    fn missing_operand_is_rejected() {
+        let err = assemble("pushimm").unwrap_err();
+        assert!(matches!(err, AsmError::MissingOperand(mnemonic, 1) if mnemonic == "pushimm"));
+    }
+
+    #[test]
+    fn unknown_mnemonic_is_rejected() {
+        let err = assemble("frobnicate").unwrap_err();
+        assert!(matches!(err, AsmError::UnknownMnemonic(mnemonic, 1) if mnemonic == "frobnicate"));
+    }
+
+    #[test]
+    fn invalid_operand_is_rejected() {
+        let err = assemble("pushimm nope").unwrap_err();
+        assert!(matches!(err, AsmError::InvalidOperand(operand, 1) if operand == "nope"));
+    }
+
+    #[test]
+    fn assembled_output_round_trips_through_the_disassembler() {
+        let source = "
+            pushimm 42
+            pushext 0x1234
+            add
+            halt
+        ";
+        let bytes = assemble(source).unwrap();
+
+        let mut memory = Memory::new();
+        for (i, byte) in bytes.iter().enumerate() {
+            memory.set((i as u16).into(), *byte);
+        }
+        let instrs = disassemble_range(&memory, 0.into(), (bytes.len() as u16).into());
+
+        let mnemonics: Vec<&str> = instrs.iter().map(|i| i.mnemonic).collect();
+        assert_eq!(mnemonics, vec!["pushimm", "pushext", "add", "halt"]);
+        assert_eq!(instrs[0].operand, Some(42));
+        assert_eq!(instrs[1].operand, Some(0x1234));
+    }
+}
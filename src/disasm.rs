@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Addr, Memory};
+
+/// A single decoded instruction, as produced by `disassemble_one`.
+pub struct Disassembled {
+    pub addr: Addr,
+    pub mnemonic: &'static str,
+    pub operand: Option<u16>,
+    /// How many bytes this instruction occupies, for advancing a disassembly range.
+    pub size: u16,
+}
+
+impl fmt::Display for Disassembled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.operand {
+            Some(operand) => write!(f, "{:?}: {} {operand:#06x}", self.addr, self.mnemonic),
+            None => write!(f, "{:?}: {}", self.addr, self.mnemonic),
+        }
+    }
+}
+
+fn read_ext(memory: &Memory, addr: Addr) -> u16 {
+    let hi = memory.get(addr) as u16;
+    let lo = memory.get(addr + 1.into()) as u16;
+    hi * 0x100 + lo
+}
+
+/// Decodes the instruction at `addr`, mirroring the decode logic in `Ssbc::step`.
+///
+/// Consumes the same number of bytes `step` would -- 1 for inherent ops, 2 for
+/// `pushimm`, 3 for the ext/jump forms -- so disassembling a range stays byte-aligned.
+pub fn disassemble_one(memory: &Memory, addr: Addr) -> Disassembled {
+    let opcode = memory.get(addr);
+    let operand_addr = addr + 1.into();
+    match opcode {
+        0 => Disassembled { addr, mnemonic: "nop", operand: None, size: 1 },
+        1 => Disassembled { addr, mnemonic: "halt", operand: None, size: 1 },
+        2 => Disassembled {
+            addr,
+            mnemonic: "pushimm",
+            operand: Some(memory.get(operand_addr) as u16),
+            size: 2,
+        },
+        3 => Disassembled {
+            addr,
+            mnemonic: "pushext",
+            operand: Some(read_ext(memory, operand_addr)),
+            size: 3,
+        },
+        4 => Disassembled { addr, mnemonic: "popinh", operand: None, size: 1 },
+        5 => Disassembled {
+            addr,
+            mnemonic: "popext",
+            operand: Some(read_ext(memory, operand_addr)),
+            size: 3,
+        },
+        6 => Disassembled {
+            addr,
+            mnemonic: "jnz",
+            operand: Some(read_ext(memory, operand_addr)),
+            size: 3,
+        },
+        7 => Disassembled {
+            addr,
+            mnemonic: "jnn",
+            operand: Some(read_ext(memory, operand_addr)),
+            size: 3,
+        },
+        8 => Disassembled { addr, mnemonic: "add", operand: None, size: 1 },
+        9 => Disassembled { addr, mnemonic: "sub", operand: None, size: 1 },
+        10 => Disassembled { addr, mnemonic: "nor", operand: None, size: 1 },
+        _ => Disassembled { addr, mnemonic: "???", operand: Some(opcode as u16), size: 1 },
+    }
+}
+
+/// Decodes every instruction from `start` up to (but not including) `end`, advancing
+/// by each instruction's own size so the decode stays aligned through multi-byte forms.
+pub fn disassemble_range(memory: &Memory, start: Addr, end: Addr) -> Vec<Disassembled> {
+    let mut out = Vec::new();
+    let mut addr = u16::from(start);
+    let end = u16::from(end);
+    while addr < end {
+        let instr = disassemble_one(memory, addr.into());
+        addr = addr.wrapping_add(instr.size);
+        out.push(instr);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_with(bytes: &[u8]) -> Memory {
+        let mut memory = Memory::new();
+        for (i, byte) in bytes.iter().enumerate() {
+            memory.set((i as u16).into(), *byte);
+        }
+        memory
+    }
+
+    #[test]
+    fn disassembles_inherent_instructions() {
+        let memory = memory_with(&[0, 1, 4, 8, 9, 10]);
+        for (addr, mnemonic) in [(0, "nop"), (1, "halt"), (2, "popinh"), (3, "add"), (4, "sub"), (5, "nor")] {
+            let instr = disassemble_one(&memory, (addr as u16).into());
+            assert_eq!(instr.mnemonic, mnemonic);
+            assert_eq!(instr.operand, None);
+            assert_eq!(instr.size, 1);
+        }
+    }
+
+    #[test]
+    fn disassembles_pushimm_with_its_immediate_byte() {
+        let memory = memory_with(&[2, 0x42]);
+        let instr = disassemble_one(&memory, 0.into());
+        assert_eq!(instr.mnemonic, "pushimm");
+        assert_eq!(instr.operand, Some(0x42));
+        assert_eq!(instr.size, 2);
+    }
+
+    #[test]
+    fn disassembles_ext_operand_forms() {
+        for (opcode, mnemonic) in [(3, "pushext"), (5, "popext"), (6, "jnz"), (7, "jnn")] {
+            let memory = memory_with(&[opcode, 0x12, 0x34]);
+            let instr = disassemble_one(&memory, 0.into());
+            assert_eq!(instr.mnemonic, mnemonic);
+            assert_eq!(instr.operand, Some(0x1234));
+            assert_eq!(instr.size, 3);
+        }
+    }
+
+    #[test]
+    fn unknown_opcode_disassembles_as_a_single_byte_placeholder() {
+        let memory = memory_with(&[0xff]);
+        let instr = disassemble_one(&memory, 0.into());
+        assert_eq!(instr.mnemonic, "???");
+        assert_eq!(instr.operand, Some(0xff));
+        assert_eq!(instr.size, 1);
+    }
+
+    #[test]
+    fn disassemble_range_advances_by_each_instructions_own_size() {
+        // nop; pushimm 9; jnz 0x0010
+        let memory = memory_with(&[0, 2, 9, 6, 0x00, 0x10]);
+        let instrs = disassemble_range(&memory, 0.into(), 6.into());
+        let mnemonics: Vec<&str> = instrs.iter().map(|i| i.mnemonic).collect();
+        assert_eq!(mnemonics, ["nop", "pushimm", "jnz"]);
+        assert_eq!(instrs[1].addr, 1.into());
+        assert_eq!(instrs[2].addr, 3.into());
+        assert_eq!(instrs[2].operand, Some(0x0010));
+    }
+
+    #[test]
+    fn disassemble_range_can_start_mid_instruction() {
+        // jnz 0x0102, but we start the range one byte into it: the high operand byte
+        // (0x01) is decoded as an opcode of its own (halt) instead of as part of jnz,
+        // and decoding continues from there rather than realigning to the original stream.
+        let memory = memory_with(&[6, 0x01, 0x02]);
+        let instrs = disassemble_range(&memory, 1.into(), 3.into());
+        assert_eq!(instrs[0].addr, 1.into());
+        assert_eq!(instrs[0].mnemonic, "halt");
+        assert_eq!(instrs[0].operand, None);
+        assert_eq!(instrs[1].addr, 2.into());
+        assert_eq!(instrs[1].mnemonic, "pushimm");
+    }
+}
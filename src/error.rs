@@ -0,0 +1,84 @@
+use alloc::string::String;
+use core::fmt;
+
+#[cfg(feature = "std")]
+use crate::asm::AsmError;
+use crate::Addr;
+
+/// Everything that can go wrong while loading, running, or driving the SSBC.
+#[derive(Debug)]
+pub enum SsbcError {
+    /// The CPU fetched an opcode with no defined instruction at the given address.
+    InvalidOpcode(u8, Addr),
+    /// The machine code being loaded doesn't fit in the 64KiB address space.
+    MachineCodeTooLarge,
+    /// A line of input wasn't a valid 8-bit binary literal.
+    BadBinaryLiteral(String),
+    /// An operator-entered address wasn't a valid hexadecimal literal.
+    InvalidAddress(String),
+    /// Assembling a source file into machine code failed.
+    #[cfg(feature = "std")]
+    Asm(AsmError),
+    /// An I/O error occurred while reading a command, loading `mac`, or talking to a port.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// Saving or loading a machine state snapshot failed.
+    #[cfg(feature = "cli")]
+    Snapshot(bincode::Error),
+}
+
+impl fmt::Display for SsbcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SsbcError::InvalidOpcode(opcode, pc) => {
+                write!(f, "invalid opcode {opcode:#04x} at {pc:?}")
+            }
+            SsbcError::MachineCodeTooLarge => write!(f, "machine code exceeds memory size"),
+            SsbcError::BadBinaryLiteral(line) => {
+                write!(f, "couldn't parse binary literal: {line:?}")
+            }
+            SsbcError::InvalidAddress(addr) => write!(f, "invalid address: {addr:?}"),
+            #[cfg(feature = "std")]
+            SsbcError::Asm(e) => write!(f, "assembly failed: {e}"),
+            #[cfg(feature = "std")]
+            SsbcError::Io(e) => write!(f, "I/O error: {e}"),
+            #[cfg(feature = "cli")]
+            SsbcError::Snapshot(e) => write!(f, "snapshot failed: {e}"),
+        }
+    }
+}
+
+impl core::error::Error for SsbcError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            SsbcError::Asm(e) => Some(e),
+            #[cfg(feature = "std")]
+            SsbcError::Io(e) => Some(e),
+            #[cfg(feature = "cli")]
+            SsbcError::Snapshot(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for SsbcError {
+    fn from(e: std::io::Error) -> Self {
+        SsbcError::Io(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<AsmError> for SsbcError {
+    fn from(e: AsmError) -> Self {
+        SsbcError::Asm(e)
+    }
+}
+
+#[cfg(feature = "cli")]
+impl From<bincode::Error> for SsbcError {
+    fn from(e: bincode::Error) -> Self {
+        SsbcError::Snapshot(e)
+    }
+}
@@ -0,0 +1,648 @@
+//! The SSBC core: its address space, memory, and CPU.
+//!
+//! This crate compiles under `#![no_std]` (using only `core`/`alloc`) so the emulator can
+//! run on embedded targets. Enable the `std` feature for the assembler and disassembler
+//! trace output, or `cli` (which implies `std`) for the interactive `SsbcCli` REPL in `main.rs`.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Debug;
+use core::ops::{Index, IndexMut};
+use derive_more::{Add, AddAssign, Sub, SubAssign};
+use serde::ser::SerializeStruct;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+pub mod disasm;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod asm;
+
+use error::SsbcError;
+
+/// An address inside the SSBC's memory space.
+/// The program, stack, ports, and program status word are all mapped inside of this.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Add, AddAssign, Sub, SubAssign)]
+pub struct Addr(core::num::Wrapping<u16>);
+impl Addr {
+    /// Cast a u16 into an address.
+    pub const fn from_u16(a: u16) -> Self {
+        Addr(core::num::Wrapping(a))
+    }
+}
+impl From<Addr> for u16 {
+    fn from(addr: Addr) -> u16 {
+        addr.0.0
+    }
+}
+impl From<Addr> for usize {
+    fn from(addr: Addr) -> usize {
+        addr.0.0 as usize
+    }
+}
+impl From<u16> for Addr {
+    fn from(a: u16) -> Self {
+        Addr::from_u16(a)
+    }
+}
+
+impl Debug for Addr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!("{:#04x}", self.0.0))
+    }
+}
+
+impl Serialize for Addr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u16(u16::from(*self))
+    }
+}
+impl<'de> Deserialize<'de> for Addr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u16::deserialize(deserializer).map(Addr::from)
+    }
+}
+
+
+/// The four ports. A and C are output, B and D are input.
+#[derive(Clone,Copy,Debug)]
+pub enum Port{
+    A,
+    B,
+    C,
+    D,
+}
+impl Port {
+    pub fn to_addr(self) -> Addr {
+        use Port::*;
+        Addr::from(match self{
+            A => 0xFFFC,
+            B => 0xFFFD,
+            C => 0xFFFE,
+            D => 0xFFFF,
+        })
+    }
+    /// The port mapped to `addr`, if any.
+    pub fn from_addr(addr: Addr) -> Option<Port> {
+        use Port::*;
+        Some(match u16::from(addr) {
+            0xFFFC => A,
+            0xFFFD => B,
+            0xFFFE => C,
+            0xFFFF => D,
+            _ => return None,
+        })
+    }
+}
+
+/// Binds the SSBC's four memory-mapped ports (`0xFFFC..=0xFFFF`) to a host device.
+///
+/// `Ssbc::step` calls `read`/`write` whenever `pushext`/`popext` target a port address,
+/// instead of touching `Memory` directly, so a host can back a port with a serial
+/// stream, a buffer, or hardware GPIO.
+pub trait PortIo {
+    /// Reads the current value held by `port`.
+    fn read(&mut self, port: Port) -> u8;
+    /// Writes `value` to `port`.
+    fn write(&mut self, port: Port, value: u8);
+}
+
+/// The default `PortIo`: each port is just a byte in memory, as the ports used to be
+/// before they were split out from `Memory`.
+#[derive(Clone, Copy, Default)]
+pub struct MemoryPorts([u8; 4]);
+impl MemoryPorts {
+    fn index(port: Port) -> usize {
+        match port {
+            Port::A => 0,
+            Port::B => 1,
+            Port::C => 2,
+            Port::D => 3,
+        }
+    }
+}
+impl PortIo for MemoryPorts {
+    fn read(&mut self, port: Port) -> u8 {
+        self.0[Self::index(port)]
+    }
+    fn write(&mut self, port: Port, value: u8) {
+        self.0[Self::index(port)] = value;
+    }
+}
+
+/// The status word's address, 0xFFFB. It is one of: 0x80 (Z), 0x40(N), or 0x00
+pub const PSW: Addr = Addr::from_u16(0xFFFB);
+/// The length of the SSBC's memory.
+const MEMORY_LENGTH: usize = u16::MAX as usize + 1;
+
+/// The 64KiB of memory that the SSBC accesses.
+#[derive(Clone)]
+pub struct Memory(pub Box<[u8; MEMORY_LENGTH]>);
+impl Memory {
+    pub fn new() -> Self {
+        Memory(Box::new([0; MEMORY_LENGTH]))
+    }
+    pub fn get(&self, address: Addr) -> u8 {
+        *self.0.index(usize::from(address))
+    }
+    pub fn get_mut(&mut self, address: Addr) -> &mut u8 {
+        self.0.index_mut(usize::from(address))
+    }
+    pub fn set(&mut self, address: Addr, value: u8) {
+        *self.get_mut(address) = value;
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serialize for Memory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0.as_ref())
+    }
+}
+impl<'de> Deserialize<'de> for Memory {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MemoryVisitor;
+        impl<'de> de::Visitor<'de> for MemoryVisitor {
+            type Value = Memory;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{MEMORY_LENGTH} bytes of SSBC memory")
+            }
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Memory, E> {
+                if v.len() != MEMORY_LENGTH {
+                    return Err(E::invalid_length(v.len(), &self));
+                }
+                let mut memory = Memory::new();
+                memory.0.copy_from_slice(v);
+                Ok(memory)
+            }
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Memory, A::Error> {
+                let mut memory = Memory::new();
+                for (i, byte) in memory.0.iter_mut().enumerate() {
+                    *byte = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+                }
+                Ok(memory)
+            }
+        }
+        deserializer.deserialize_bytes(MemoryVisitor)
+    }
+}
+
+pub struct Ssbc {
+    memory: Memory,
+    /// The host binding for ports A/B/C/D.
+    ports: Box<dyn PortIo>,
+    /// Program Counter. Set to 0x0000 by .reset()
+    pc: Addr,
+    /// Stack Pointer. Set to 0xFFFA by .reset()
+    sp: Addr,
+    /// Fault flag is raised when an instruction is invalid.
+    fault: bool,
+    /// Halt flag is raised by halt instruction.
+    halt: bool,
+    /// The opcode and program counter that caused the last fault, if any.
+    last_fault: Option<(u8, Addr)>,
+    /// When set, `step` prints each decoded instruction before executing it.
+    trace: bool,
+    /// PC addresses that `run` should stop at.
+    breakpoints: Vec<Addr>,
+    /// Addresses `run` watches for a change, alongside the value last observed there.
+    watchpoints: Vec<(Addr, u8)>,
+}
+
+impl Default for Ssbc {
+    fn default() -> Self {
+        Ssbc {
+            memory: Memory::default(),
+            ports: Box::new(MemoryPorts::default()),
+            pc: Addr::default(),
+            sp: Addr::default(),
+            fault: false,
+            halt: false,
+            last_fault: None,
+            trace: false,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+        }
+    }
+}
+
+/// Why `run` stopped.
+#[derive(Debug, Clone, Copy)]
+pub enum StopReason {
+    /// `pc` reached an address in the breakpoint set.
+    Breakpoint(Addr),
+    /// A watched address's value changed from the first value to the second.
+    Watchpoint(Addr, u8, u8),
+    /// The CPU executed a `halt` instruction.
+    Halt,
+    /// The CPU faulted on an invalid opcode.
+    Fault,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Breakpoint(addr) => write!(f, "hit breakpoint at {addr:?}"),
+            StopReason::Watchpoint(addr, old, new) => {
+                write!(f, "watchpoint at {addr:?} changed {old:#04x} -> {new:#04x}")
+            }
+            StopReason::Halt => write!(f, "halted"),
+            StopReason::Fault => write!(f, "faulted"),
+        }
+    }
+}
+
+/// The part of `Ssbc`'s state that's meaningful to snapshot: the bound `PortIo` is
+/// host-specific and not serialized, and `last_fault`/`trace`/breakpoints/watchpoints
+/// are debug-only state that a freshly-restored machine starts without.
+#[derive(Serialize, Deserialize)]
+struct SsbcSnapshot {
+    memory: Memory,
+    pc: Addr,
+    sp: Addr,
+    fault: bool,
+    halt: bool,
+}
+
+impl Serialize for Ssbc {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Ssbc", 5)?;
+        state.serialize_field("memory", &self.memory)?;
+        state.serialize_field("pc", &self.pc)?;
+        state.serialize_field("sp", &self.sp)?;
+        state.serialize_field("fault", &self.fault)?;
+        state.serialize_field("halt", &self.halt)?;
+        state.end()
+    }
+}
+impl<'de> Deserialize<'de> for Ssbc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = SsbcSnapshot::deserialize(deserializer)?;
+        Ok(Ssbc {
+            memory: snapshot.memory,
+            ports: Box::new(MemoryPorts::default()),
+            pc: snapshot.pc,
+            sp: snapshot.sp,
+            fault: snapshot.fault,
+            halt: snapshot.halt,
+            last_fault: None,
+            trace: false,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+        })
+    }
+}
+
+impl Ssbc {
+    /// Rebinds the SSBC's ports to a new host device.
+    pub fn set_ports(&mut self, ports: impl PortIo + 'static) {
+        self.ports = Box::new(ports);
+    }
+    /// Retrieves the Program Status Word.
+    pub fn get_psw(&self) -> u8 {
+        self.memory.get(PSW)
+    }
+    /// Clears flags and program counter, sets stack pointer to 0xFFFA
+    pub fn reset(&mut self) {
+        self.pc = 0x0000.into();
+        self.sp = 0xFFFA.into();
+        self.fault = false;
+        self.halt = false;
+        self.last_fault = None;
+    }
+    /// The opcode and program counter that caused the last fault, if any.
+    pub fn last_fault(&self) -> Option<(u8, Addr)> {
+        self.last_fault
+    }
+    /// Whether `step` currently prints each decoded instruction before executing it.
+    pub fn trace(&self) -> bool {
+        self.trace
+    }
+    /// Enables or disables per-instruction trace output on `step`.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+    /// Reads the memory the CPU operates on, for commands like disassembly that
+    /// inspect state without executing it.
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+    /// Mutable access to the memory the CPU operates on, for loading programs.
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+    /// The current stack pointer.
+    pub fn sp(&self) -> Addr {
+        self.sp
+    }
+    /// Whether the CPU is currently faulted.
+    pub fn fault(&self) -> bool {
+        self.fault
+    }
+    /// Whether the CPU is currently halted.
+    pub fn halt(&self) -> bool {
+        self.halt
+    }
+    /// Sets a breakpoint: `run` will stop once `pc` reaches `addr`.
+    pub fn add_breakpoint(&mut self, addr: Addr) {
+        if !self.breakpoints.contains(&addr) {
+            self.breakpoints.push(addr);
+        }
+    }
+    /// Clears a previously set breakpoint, if any.
+    pub fn remove_breakpoint(&mut self, addr: Addr) {
+        self.breakpoints.retain(|&bp| bp != addr);
+    }
+    /// The currently set breakpoints.
+    pub fn breakpoints(&self) -> &[Addr] {
+        &self.breakpoints
+    }
+    /// Watches `addr`: `run` will stop the first time its value differs from what it
+    /// holds right now.
+    pub fn add_watchpoint(&mut self, addr: Addr) {
+        if !self.watchpoints.iter().any(|(wp, _)| *wp == addr) {
+            let value = self.memory.get(addr);
+            self.watchpoints.push((addr, value));
+        }
+    }
+    /// Clears a previously set watchpoint, if any.
+    pub fn remove_watchpoint(&mut self, addr: Addr) {
+        self.watchpoints.retain(|(wp, _)| *wp != addr);
+    }
+    /// The currently set watchpoints, alongside the value last observed at each address.
+    pub fn watchpoints(&self) -> &[(Addr, u8)] {
+        &self.watchpoints
+    }
+    /// Reads the current value of `port` from the bound `PortIo`.
+    pub fn read_port(&mut self, port: Port) -> u8 {
+        self.ports.read(port)
+    }
+    /// Writes `value` to `port` on the bound `PortIo`.
+    pub fn write_port(&mut self, port: Port, value: u8) {
+        self.ports.write(port, value);
+    }
+    /// Read a single byte from the program counter, moving the pc 1 forward
+    fn read_ir(&mut self) -> u8 {
+        let ir = self.memory.get(self.pc);
+        self.pc += 1.into();
+        ir
+    }
+    /// Read two bytes at the program counter, moving the pc 2 forward
+    fn read_ext(&mut self) -> u16 {
+        let hi = self.memory.get(self.pc)   as u16;
+        let lo = self.memory.get(self.pc+1.into()) as u16;
+        self.pc += 2.into();
+        hi*0x100+lo
+    }
+    /// Reads the byte at a decoded `ext` address, routing port addresses through `ports`.
+    fn get_ext(&mut self, addr: Addr) -> u8 {
+        match Port::from_addr(addr) {
+            Some(port) => self.ports.read(port),
+            None => self.memory.get(addr),
+        }
+    }
+    /// Writes a byte to a decoded `ext` address, routing port addresses through `ports`.
+    fn set_ext(&mut self, addr: Addr, value: u8) {
+        match Port::from_addr(addr) {
+            Some(port) => self.ports.write(port, value),
+            None => self.memory.set(addr, value),
+        }
+    }
+    fn update_psw(&mut self, val: u8) {
+        self.memory.set(
+            PSW,
+            if val>128{ 0x40 }else if val==0 { 0x80 } else { 0x00 }
+        );
+    }
+    #[cfg(feature = "std")]
+    fn trace_step(&self, opcode_addr: Addr) {
+        let instr = disasm::disassemble_one(&self.memory, opcode_addr);
+        std::println!("{instr}  [sp={:?} psw={:#04x}]", self.sp, self.get_psw());
+    }
+    #[cfg(not(feature = "std"))]
+    fn trace_step(&self, _opcode_addr: Addr) {}
+    /// Steps by a single instruction
+    /// Referred to as "break" in the CLI
+    pub fn step(&mut self) -> Result<(), SsbcError> {
+        if self.fault || self.halt {
+            return Ok(());
+        }
+        let opcode_addr = self.pc;
+        if self.trace {
+            self.trace_step(opcode_addr);
+        }
+        match self.read_ir() {
+            // nop
+            0 => (),
+            // halt
+            1 => self.halt = true,
+            // pushimm
+            2 => {
+                let ir = self.read_ir();
+                self.memory.set(self.sp, ir);
+                self.sp -= 1.into();
+            },
+            // pushext
+            3 => {
+                let ext: Addr = self.read_ext().into();
+                let value = self.get_ext(ext);
+                self.memory.set(self.sp, value);
+                self.sp -= 1.into();
+            },
+            // popinh
+            4 => {
+                self.sp += 1.into();
+            },
+            // popext
+            5 => {
+                let ext: Addr = self.read_ext().into();
+                let pop = self.memory.get(self.sp+1.into());
+                self.set_ext(ext, pop);
+                self.sp += 1.into();
+            },
+            // jnz
+            6 => {
+                let ext = self.read_ext();
+                if self.memory.get(PSW) != 0x80 {
+                    self.pc = ext.into();
+                }
+            },
+            // jnn
+            7 => {
+                let ext = self.read_ext();
+                if self.memory.get(PSW) != 0x40 {
+                    self.pc = ext.into();
+                }
+            },
+            // add
+            8 => {
+                let result = self.memory.get(self.sp+2.into()).wrapping_add(self.memory.get(self.sp+1.into()));
+                self.memory.set(self.sp+2.into(), result);
+                self.update_psw(result);
+                self.sp += 1.into();
+            },
+            // sub
+            9 => {
+                let result = self.memory.get(self.sp+1.into()).wrapping_sub(self.memory.get(self.sp+2.into()));
+                self.memory.set(self.sp+2.into(), result);
+                self.update_psw(result);
+                self.sp += 1.into();
+            },
+            // nor
+            10 => {
+                let bw_or = self.memory.get(self.sp+2.into()) | self.memory.get(self.sp+1.into());
+                self.memory.set(self.sp+2.into(), !bw_or);
+                self.sp+=1.into();
+            },
+            // fault
+            opcode => {
+                self.fault = true;
+                self.last_fault = Some((opcode, opcode_addr));
+                return Err(SsbcError::InvalidOpcode(opcode, opcode_addr));
+            },
+        }
+        Ok(())
+    }
+    /// Runs instructions until halt, fault, a breakpoint, or a watchpoint trips,
+    /// reporting which one stopped execution.
+    pub fn run(&mut self) -> Result<StopReason, SsbcError> {
+        loop {
+            match self.step() {
+                Ok(()) => (),
+                Err(SsbcError::InvalidOpcode(..)) => return Ok(StopReason::Fault),
+                Err(e) => return Err(e),
+            }
+            if self.halt {
+                return Ok(StopReason::Halt);
+            }
+            if self.fault {
+                return Ok(StopReason::Fault);
+            }
+            if self.breakpoints.contains(&self.pc) {
+                return Ok(StopReason::Breakpoint(self.pc));
+            }
+            for (addr, seen) in self.watchpoints.iter_mut() {
+                let current = self.memory.get(*addr);
+                if current != *seen {
+                    let previous = *seen;
+                    *seen = current;
+                    return Ok(StopReason::Watchpoint(*addr, previous, current));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads `program` starting at address 0 and resets pc/sp to their usual reset values.
+    fn load(program: &[u8]) -> Ssbc {
+        let mut ssbc = Ssbc::default();
+        ssbc.reset();
+        for (i, byte) in program.iter().enumerate() {
+            ssbc.memory.set((i as u16).into(), *byte);
+        }
+        ssbc
+    }
+
+    #[test]
+    fn run_stops_at_a_breakpoint() {
+        // nop; nop; halt
+        let mut ssbc = load(&[0, 0, 1]);
+        ssbc.add_breakpoint(1.into());
+        let reason = ssbc.run().unwrap();
+        assert!(matches!(reason, StopReason::Breakpoint(addr) if addr == 1.into()));
+        assert_eq!(ssbc.pc, 1.into());
+    }
+
+    #[test]
+    fn run_stops_at_a_watchpoint() {
+        // pushimm 0x42; halt
+        let mut ssbc = load(&[2, 0x42, 1]);
+        let sp = ssbc.sp;
+        ssbc.add_watchpoint(sp);
+        let reason = ssbc.run().unwrap();
+        assert!(matches!(
+            reason,
+            StopReason::Watchpoint(addr, 0, 0x42) if addr == sp
+        ));
+    }
+
+    #[test]
+    fn run_reports_fault_instead_of_erroring_on_an_invalid_opcode() {
+        // opcode 0xff is not a valid instruction
+        let mut ssbc = load(&[0xff]);
+        let reason = ssbc.run().unwrap();
+        assert!(matches!(reason, StopReason::Fault));
+        assert!(ssbc.fault());
+        assert_eq!(ssbc.last_fault(), Some((0xff, 0.into())));
+    }
+
+    #[test]
+    fn run_stops_on_halt() {
+        // halt
+        let mut ssbc = load(&[1]);
+        let reason = ssbc.run().unwrap();
+        assert!(matches!(reason, StopReason::Halt));
+        assert!(ssbc.halt());
+    }
+
+    #[test]
+    fn pushext_and_popext_route_port_addresses_through_ports_not_memory() {
+        let port_a = Port::A.to_addr();
+        assert_eq!(port_a, 0xFFFCu16.into());
+        // pushimm 0x42; popext <port A>; pushext <port A>; halt
+        let mut ssbc = load(&[
+            2, 0x42,
+            5, 0xFF, 0xFC,
+            3, 0xFF, 0xFC,
+            1,
+        ]);
+        ssbc.run().unwrap();
+
+        // The value went through `ports`, never touching the underlying memory cell.
+        assert_eq!(ssbc.memory.get(port_a), 0);
+        assert_eq!(ssbc.read_port(Port::A), 0x42);
+
+        // And reading it back via `pushext` pulled from `ports` too: the byte landed
+        // back on the stack even though `memory[port_a]` was never written.
+        assert_eq!(ssbc.memory.get(ssbc.sp + 1.into()), 0x42);
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn ssbc_serde_round_trip_preserves_cpu_state_and_resets_debug_state() {
+        // pushimm 7; halt
+        let mut ssbc = load(&[2, 7, 1]);
+        ssbc.step().unwrap(); // leaves pc/sp/memory mid-program, before halting
+        ssbc.add_breakpoint(0.into());
+        ssbc.add_watchpoint(0.into());
+        ssbc.set_trace(true);
+
+        let bytes = bincode::serialize(&ssbc).unwrap();
+        let restored: Ssbc = bincode::deserialize(&bytes).unwrap();
+
+        assert_eq!(restored.pc, ssbc.pc);
+        assert_eq!(restored.sp, ssbc.sp);
+        assert_eq!(restored.fault, ssbc.fault);
+        assert_eq!(restored.halt, ssbc.halt);
+        for addr in 0..=u16::MAX {
+            assert_eq!(restored.memory.get(addr.into()), ssbc.memory.get(addr.into()));
+        }
+
+        assert!(restored.breakpoints().is_empty());
+        assert!(restored.watchpoints().is_empty());
+        assert!(!restored.trace());
+    }
+}